@@ -3,11 +3,142 @@ use anyhow::{anyhow, Context, Result};
 use log::warn;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread::spawn;
+use std::time::Duration;
+
+/// Maximum number of requests accepted in one batch envelope. Without a
+/// cap, a single ndjson line could ask for an unbounded number of
+/// spawned request threads.
+const MAX_BATCH_REQUESTS: usize = 256;
+
+/// Worker threads backing each server's request-dispatch pool.
+const DISPATCH_POOL_THREADS: usize = 16;
+
+/// Maximum number of per-item threads `dispatch_batch` will have running at
+/// once for a single batch. `check_batch_len` only bounds the *count* of
+/// requests in a batch; without this, a single batch line could still spin
+/// up `MAX_BATCH_REQUESTS` bare OS threads simultaneously. Chunking to this
+/// size caps concurrency the same way `WorkerPool` does for per-line
+/// dispatch, without routing through `WorkerPool` itself, since a pool
+/// worker that's already running `dispatch_batch` would deadlock waiting on
+/// sub-jobs submitted back into its own saturated pool.
+const MAX_CONCURRENT_BATCH_THREADS: usize = DISPATCH_POOL_THREADS;
+
+/// How many dispatched-but-not-yet-running requests `WorkerPool::execute`
+/// will queue before it starts blocking the caller. Bounding this (rather
+/// than using an unbounded channel) is what gives `serve`'s read loop real
+/// backpressure: once the queue is full, submitting the next line's work
+/// blocks until a worker frees up, instead of the read loop racing ahead
+/// and accumulating unbounded queued work.
+const DISPATCH_POOL_QUEUE_DEPTH: usize = 64;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed-size pool of worker threads shared by a whole `ScxStatsServer`
+/// instance. `serve` submits each line's dispatch work here instead of
+/// spawning an OS thread per line, so a client pipelining requests faster
+/// than the server can answer them stalls on `execute` rather than causing
+/// unbounded thread or memory growth. This matters most for the TCP
+/// transport, which (unlike the Unix socket) has no connection-level
+/// authentication to limit who can do this.
+#[derive(Clone)]
+struct WorkerPool {
+    jobs: mpsc::SyncSender<Job>,
+}
+
+impl WorkerPool {
+    fn new(threads: usize, queue_depth: usize) -> Self {
+        let (jobs, receiver) = mpsc::sync_channel::<Job>(queue_depth);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..threads {
+            Self::spawn_worker(receiver.clone());
+        }
+        Self { jobs }
+    }
+
+    /// Runs the worker loop on a fresh thread. A panicking job is caught so
+    /// it can't unwind the worker thread itself: without this, one bad
+    /// handler would permanently shrink the pool by one worker every time it
+    /// panics, eventually leaving `execute` with nobody left to hand jobs to.
+    fn spawn_worker(receiver: Arc<Mutex<mpsc::Receiver<Job>>>) {
+        spawn(move || loop {
+            let job = receiver.lock().unwrap().recv();
+            match job {
+                Ok(job) => {
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                }
+                Err(_) => return,
+            }
+        });
+    }
+
+    /// Submits `job`, blocking the caller once the pool's queue is full.
+    fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        let _ = self.jobs.send(Box::new(job));
+    }
+}
+
+/// A stream accepted from either a `UnixListener` or a `TcpListener`. This
+/// lets `ScxStatsServerInner::serve` stay transport-agnostic: it only ever
+/// deals in `Read + Write`, regardless of which listener produced the
+/// connection.
+trait ScxStatsStream: Read + Write + Send {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn ScxStatsStream>>;
+
+    /// Shuts down the read half of the connection, unblocking anyone parked
+    /// in a `read`/`read_line` call on a clone of this stream. Used to tear
+    /// down a reader-watcher thread when the write side gives up first.
+    fn shutdown_read(&self) -> std::io::Result<()>;
+}
+
+impl ScxStatsStream for UnixStream {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn ScxStatsStream>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+
+    fn shutdown_read(&self) -> std::io::Result<()> {
+        self.shutdown(std::net::Shutdown::Read)
+    }
+}
+
+impl ScxStatsStream for TcpStream {
+    fn try_clone_box(&self) -> std::io::Result<Box<dyn ScxStatsStream>> {
+        Ok(Box::new(self.try_clone()?))
+    }
+
+    fn shutdown_read(&self) -> std::io::Result<()> {
+        self.shutdown(std::net::Shutdown::Read)
+    }
+}
+
+/// Binds either a Unix domain socket (the default, for local monitoring) or
+/// a TCP socket (for monitoring a scheduler running on another host), while
+/// producing the same `ScxStatsStream` connections either way.
+enum ScxStatsListener {
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+impl ScxStatsListener {
+    fn accept(&self) -> std::io::Result<Box<dyn ScxStatsStream>> {
+        match self {
+            Self::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(Box::new(stream))
+            }
+            Self::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
 
 type StatMap = BTreeMap<
     String,
@@ -19,6 +150,12 @@ pub struct ScxStatsRequest {
     pub req: String,
     #[serde(default)]
     pub args: BTreeMap<String, String>,
+    /// Opaque client-chosen correlation id (string or integer), echoed back
+    /// verbatim in the corresponding `ScxStatsResponse`. Lets a client
+    /// pipeline several requests on one connection instead of waiting for
+    /// each reply before sending the next.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<serde_json::Value>,
 }
 
 impl ScxStatsRequest {
@@ -26,6 +163,7 @@ impl ScxStatsRequest {
         Self {
             req: req.to_string(),
             args: args.into_iter().collect(),
+            id: None,
         }
     }
 }
@@ -33,9 +171,44 @@ impl ScxStatsRequest {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ScxStatsResponse {
     pub errno: i32,
+    /// Echo of the request's `id`, if it had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<serde_json::Value>,
+    /// Stable, language-agnostic name for the failure cause (e.g.
+    /// `"NotFound"`, `"InvalidData"`), set alongside `errno` on error
+    /// responses so non-Rust clients can branch on something other than a
+    /// raw errno. `None` on success, and also on errors whose cause has no
+    /// symbolic name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_class: Option<String>,
     pub args: BTreeMap<String, serde_json::Value>,
 }
 
+/// Protocol version implemented by this crate. Bump the minor version for
+/// backward-compatible additions (e.g. a new request type) and the major
+/// version for breaking changes to the request/response framing.
+pub const SCX_STATS_PROTOCOL_VERSION: (u32, u32) = (1, 0);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScxStatsVersionResp {
+    pub server_version: String,
+    pub protocol_version: (u32, u32),
+    pub capabilities: Vec<String>,
+}
+
+impl ScxStatsVersionResp {
+    /// Returns `true` if a client built against `required` can talk to a
+    /// server advertising `self.protocol_version`, i.e. the major versions
+    /// match and the server's minor is at least as new as required.
+    pub fn supports(&self, required: (u32, u32)) -> bool {
+        self.protocol_version.0 == required.0 && self.protocol_version.1 >= required.1
+    }
+
+    pub fn has_capability(&self, cap: &str) -> bool {
+        self.capabilities.iter().any(|c| c == cap)
+    }
+}
+
 pub struct ScxStatsErrno(pub i32);
 
 impl std::fmt::Display for ScxStatsErrno {
@@ -50,25 +223,80 @@ impl std::fmt::Debug for ScxStatsErrno {
     }
 }
 
+/// Context marker for an error whose cause is that some named entity (e.g.
+/// a stat target) doesn't exist, so `classify_error` can report it as
+/// `"NotFound"` rather than the generic `"InvalidInput"`.
+#[derive(Debug)]
+struct ScxStatsNotFound;
+
+impl std::fmt::Display for ScxStatsNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "not found")
+    }
+}
+
+/// Maps an error's cause to a stable symbolic `error_class` name and the
+/// `errno` to report alongside it, so non-Rust clients can branch on
+/// something more descriptive than a bare integer.
+fn classify_error(e: &anyhow::Error) -> (i32, Option<String>) {
+    // Values attached with `.context()` are only reachable through
+    // `anyhow::Error::downcast_ref`, not through the items of `chain()`.
+    if e.downcast_ref::<ScxStatsNotFound>().is_some() {
+        return (libc::EINVAL, Some("NotFound".to_string()));
+    }
+    if let Some(errno) = e.downcast_ref::<ScxStatsErrno>() {
+        let class = match errno.0 {
+            libc::EINVAL => Some("InvalidInput".to_string()),
+            _ => None,
+        };
+        return (errno.0, class);
+    }
+
+    for cause in e.chain() {
+        if cause.downcast_ref::<serde_json::Error>().is_some() {
+            return (libc::EINVAL, Some("InvalidData".to_string()));
+        }
+        if let Some(io_err) = cause.downcast_ref::<std::io::Error>() {
+            return (
+                io_err.raw_os_error().unwrap_or(libc::EIO),
+                Some(format!("{:?}", io_err.kind())),
+            );
+        }
+    }
+
+    (libc::EINVAL, None)
+}
+
 struct ScxStatsServerData {
     stats_meta: BTreeMap<String, ScxStatsMeta>,
     stats: StatMap,
+    version: String,
+    capabilities: Vec<String>,
 }
 
 struct ScxStatsServerInner {
-    listener: UnixListener,
+    listener: ScxStatsListener,
     data: Arc<Mutex<ScxStatsServerData>>,
+    pool: WorkerPool,
 }
 
 impl ScxStatsServerInner {
     fn new(
-        listener: UnixListener,
+        listener: ScxStatsListener,
         stats_meta: BTreeMap<String, ScxStatsMeta>,
         stats: StatMap,
+        version: String,
+        capabilities: Vec<String>,
     ) -> Self {
         Self {
             listener,
-            data: Arc::new(Mutex::new(ScxStatsServerData { stats_meta, stats })),
+            data: Arc::new(Mutex::new(ScxStatsServerData {
+                stats_meta,
+                stats,
+                version,
+                capabilities,
+            })),
+            pool: WorkerPool::new(DISPATCH_POOL_THREADS, DISPATCH_POOL_QUEUE_DEPTH),
         }
     }
 
@@ -78,18 +306,29 @@ impl ScxStatsServerInner {
     {
         Ok(ScxStatsResponse {
             errno,
+            id: None,
+            error_class: None,
             args: [("resp".into(), serde_json::to_value(resp)?)]
                 .into_iter()
                 .collect(),
         })
     }
 
-    fn handle_request(
-        line: String,
+    fn build_err_resp(errno: i32, error_class: Option<String>, msg: &str) -> Result<ScxStatsResponse> {
+        Ok(ScxStatsResponse {
+            errno,
+            id: None,
+            error_class,
+            args: [("resp".into(), serde_json::to_value(msg)?)]
+                .into_iter()
+                .collect(),
+        })
+    }
+
+    fn eval_request(
+        req: ScxStatsRequest,
         data: &Arc<Mutex<ScxStatsServerData>>,
     ) -> Result<ScxStatsResponse> {
-        let req: ScxStatsRequest = serde_json::from_str(&line)?;
-
         match req.req.as_str() {
             "stats" => {
                 let target = match req.args.get("target") {
@@ -100,7 +339,8 @@ impl ScxStatsServerInner {
                 let handler = match data.lock().unwrap().stats.get(target) {
                     Some(v) => v.clone(),
                     None => Err(anyhow!("unknown stat target {:?}", req)
-                        .context(ScxStatsErrno(libc::EINVAL)))?,
+                        .context(ScxStatsErrno(libc::EINVAL))
+                        .context(ScxStatsNotFound))?,
                 };
 
                 let resp = handler.lock().unwrap()(&req.args)?;
@@ -108,12 +348,203 @@ impl ScxStatsServerInner {
                 Self::build_resp(0, &resp)
             }
             "stats_meta" => Ok(Self::build_resp(0, &data.lock().unwrap().stats_meta)?),
+            "version" => {
+                let data = data.lock().unwrap();
+                Self::build_resp(
+                    0,
+                    &ScxStatsVersionResp {
+                        server_version: data.version.clone(),
+                        protocol_version: SCX_STATS_PROTOCOL_VERSION,
+                        capabilities: data.capabilities.clone(),
+                    },
+                )
+            }
             req => Err(anyhow!("unknown command {:?}", req).context(ScxStatsErrno(libc::EINVAL)))?,
         }
     }
 
-    fn serve(mut stream: UnixStream, data: Arc<Mutex<ScxStatsServerData>>) -> Result<()> {
-        let mut reader = BufReader::new(stream.try_clone()?);
+    /// Evaluates a single request, turning any error into an error response
+    /// rather than propagating it, so one bad request in a batch doesn't
+    /// take down the rest.
+    fn dispatch_request(
+        req: ScxStatsRequest,
+        data: &Arc<Mutex<ScxStatsServerData>>,
+    ) -> ScxStatsResponse {
+        let id = req.id.clone();
+        let mut resp = match Self::eval_request(req, data) {
+            Ok(resp) => resp,
+            Err(e) => {
+                let (errno, error_class) = classify_error(&e);
+                Self::build_err_resp(errno, error_class, &format!("{:?}", &e))
+                    .expect("formatting an error response should never fail")
+            }
+        };
+        resp.id = id;
+        resp
+    }
+
+    /// Dispatches a batch of requests, preserving the original order in the
+    /// returned `Vec`. Each request runs on its own thread unless `sequence`
+    /// is set, in which case they run one after another so handlers with
+    /// side effects aren't reordered or raced against each other. The
+    /// concurrent path runs at most `MAX_CONCURRENT_BATCH_THREADS` requests
+    /// at a time, in order-preserving chunks, so a large batch can't spin up
+    /// one thread per request all at once.
+    fn dispatch_batch(
+        reqs: Vec<ScxStatsRequest>,
+        sequence: bool,
+        data: &Arc<Mutex<ScxStatsServerData>>,
+    ) -> Vec<ScxStatsResponse> {
+        if sequence {
+            return reqs
+                .into_iter()
+                .map(|req| Self::dispatch_request(req, data))
+                .collect();
+        }
+
+        reqs.chunks(MAX_CONCURRENT_BATCH_THREADS)
+            .flat_map(|chunk| {
+                chunk
+                    .iter()
+                    .cloned()
+                    .map(|req| {
+                        let data = data.clone();
+                        spawn(move || Self::dispatch_request(req, &data))
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|handle| handle.join().expect("request handler thread panicked"))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Parses one ndjson line, which may be a single `ScxStatsRequest`, a
+    /// bare JSON array of them, or a `{"batch": [...], "sequence": bool}`
+    /// envelope, and returns the serialized response (or array of
+    /// responses, for a batch) to write back.
+    fn handle_line(line: String, data: &Arc<Mutex<ScxStatsServerData>>) -> Result<String> {
+        let val: serde_json::Value = serde_json::from_str(&line)?;
+
+        let resp = if let Some(batch) = val.get("batch") {
+            let reqs: Vec<ScxStatsRequest> = serde_json::from_value(batch.clone())?;
+            Self::check_batch_len(&reqs)?;
+            let sequence = val
+                .get("sequence")
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or(false);
+            serde_json::to_value(Self::dispatch_batch(reqs, sequence, data))?
+        } else if val.is_array() {
+            let reqs: Vec<ScxStatsRequest> = serde_json::from_value(val)?;
+            Self::check_batch_len(&reqs)?;
+            serde_json::to_value(Self::dispatch_batch(reqs, false, data))?
+        } else {
+            let req: ScxStatsRequest = serde_json::from_value(val)?;
+            serde_json::to_value(Self::dispatch_request(req, data))?
+        };
+
+        Ok(serde_json::to_string(&resp)? + "\n")
+    }
+
+    /// Rejects batch envelopes larger than `MAX_BATCH_REQUESTS`, so one
+    /// ndjson line can't force the server to spawn an unbounded number of
+    /// per-item request threads (see `dispatch_batch`).
+    fn check_batch_len(reqs: &[ScxStatsRequest]) -> Result<()> {
+        if reqs.len() > MAX_BATCH_REQUESTS {
+            return Err(anyhow!(
+                "batch of {} requests exceeds the limit of {}",
+                reqs.len(),
+                MAX_BATCH_REQUESTS
+            )
+            .context(ScxStatsErrno(libc::EINVAL)));
+        }
+        Ok(())
+    }
+
+    /// Pushes `stats` responses for `req`'s target on `stream` every
+    /// `interval_ms` until the client disconnects or sends a `"cancel"`
+    /// request on the same connection. The read half is watched on its own
+    /// thread so a slow or absent client doesn't stall the timer, and
+    /// either a write error or EOF on the read half ends the loop cleanly.
+    /// A write error also shuts down the read half, so the reader thread
+    /// isn't left parked in `read_line` forever on a half-closed connection
+    /// where only the write direction failed.
+    fn run_monitor(
+        req: ScxStatsRequest,
+        mut stream: Box<dyn ScxStatsStream>,
+        mut reader: BufReader<Box<dyn ScxStatsStream>>,
+        data: &Arc<Mutex<ScxStatsServerData>>,
+    ) -> Result<()> {
+        let target = req
+            .args
+            .get("target")
+            .cloned()
+            .unwrap_or_else(|| "top".to_string());
+        let interval_ms: u64 = req
+            .args
+            .get("interval_ms")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1000);
+
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        let reader_cancelled = cancelled.clone();
+        let reader_handle = spawn(move || {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                match reader.read_line(&mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        let is_cancel = serde_json::from_str::<ScxStatsRequest>(&line)
+                            .map(|req| req.req == "cancel")
+                            .unwrap_or(false);
+                        if is_cancel {
+                            break;
+                        }
+                    }
+                }
+            }
+            reader_cancelled.store(true, Ordering::Relaxed);
+        });
+
+        let stats_req = ScxStatsRequest::new("stats", vec![("target".to_string(), target)]);
+        while !cancelled.load(Ordering::Relaxed) {
+            let resp = Self::dispatch_request(stats_req.clone(), data);
+            let output = serde_json::to_string(&resp)? + "\n";
+            if stream.write_all(output.as_bytes()).is_err() {
+                // The reader thread only ever sees this side's writes
+                // failing if the peer only reset the write direction (e.g.
+                // a half-closed TCP connection), so it could otherwise sit
+                // in `read_line` forever. Shut its read half down so it
+                // wakes up with an error/EOF and joins promptly.
+                let _ = stream.shutdown_read();
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(interval_ms));
+        }
+
+        cancelled.store(true, Ordering::Relaxed);
+        let _ = reader_handle.join();
+        Ok(())
+    }
+
+    /// Reads ndjson requests off `stream` and dispatches each onto `pool`
+    /// rather than a freshly spawned thread, so a slow request (or a slow
+    /// batch) doesn't block the read loop from picking up the next one,
+    /// while a flood of lines is bounded by the pool's queue depth instead
+    /// of growing threads/memory without limit. Workers share `writer` to
+    /// write their response line as soon as it's ready, tagged with the
+    /// request's `id` if it had one — this lets a single connection
+    /// interleave replies instead of forcing strict request/response
+    /// lockstep.
+    fn serve(
+        mut stream: Box<dyn ScxStatsStream>,
+        data: Arc<Mutex<ScxStatsServerData>>,
+        pool: WorkerPool,
+    ) -> Result<()> {
+        let mut reader = BufReader::new(stream.try_clone_box()?);
+        let writer: Arc<Mutex<Box<dyn ScxStatsStream>>> = Arc::new(Mutex::new(stream.try_clone_box()?));
 
         loop {
             let mut line = String::new();
@@ -122,36 +553,47 @@ impl ScxStatsServerInner {
                 return Ok(());
             }
 
-            let resp = match Self::handle_request(line, &data) {
-                Ok(v) => v,
-                Err(e) => {
-                    let errno = match e.downcast_ref::<ScxStatsErrno>() {
-                        Some(e) if e.0 != 0 => e.0,
-                        _ => libc::EINVAL,
-                    };
-                    Self::build_resp(errno, &format!("{:?}", &e))?
+            if let Ok(req) = serde_json::from_str::<ScxStatsRequest>(&line) {
+                if req.req == "monitor" {
+                    return Self::run_monitor(req, stream, reader, &data);
                 }
-            };
+            }
 
-            let output = serde_json::to_string(&resp)? + "\n";
-            stream.write_all(output.as_bytes())?;
+            let data = data.clone();
+            let writer = writer.clone();
+            pool.execute(move || {
+                let output = match Self::handle_line(line, &data) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        let (errno, error_class) = classify_error(&e);
+                        let resp = Self::build_err_resp(errno, error_class, &format!("{:?}", &e))
+                            .expect("formatting an error response should never fail");
+                        serde_json::to_string(&resp)
+                            .expect("serializing a response should never fail")
+                            + "\n"
+                    }
+                };
+
+                if writer.lock().unwrap().write_all(output.as_bytes()).is_err() {
+                    warn!("failed to write stat response");
+                }
+            });
         }
     }
 
     fn listen(self) {
         loop {
-            for stream in self.listener.incoming() {
-                match stream {
-                    Ok(stream) => {
-                        let data = self.data.clone();
-                        spawn(move || {
-                            if let Err(e) = Self::serve(stream, data) {
-                                warn!("stat communication errored ({})", &e);
-                            }
-                        });
-                    }
-                    Err(e) => warn!("failed to accept stat connection ({})", &e),
+            match self.listener.accept() {
+                Ok(stream) => {
+                    let data = self.data.clone();
+                    let pool = self.pool.clone();
+                    spawn(move || {
+                        if let Err(e) = Self::serve(stream, data, pool) {
+                            warn!("stat communication errored ({})", &e);
+                        }
+                    });
                 }
+                Err(e) => warn!("failed to accept stat connection ({})", &e),
             }
         }
     }
@@ -162,9 +604,12 @@ pub struct ScxStatsServer {
     sched_path: PathBuf,
     stats_path: PathBuf,
     path: Option<PathBuf>,
+    tcp_addr: Option<SocketAddr>,
 
     stats_meta_holder: BTreeMap<String, ScxStatsMeta>,
     stats_holder: StatMap,
+    version_holder: String,
+    capabilities_holder: Vec<String>,
 }
 
 impl ScxStatsServer {
@@ -174,9 +619,18 @@ impl ScxStatsServer {
             sched_path: PathBuf::from("root"),
             stats_path: PathBuf::from("stats"),
             path: None,
+            tcp_addr: None,
 
             stats_meta_holder: BTreeMap::new(),
             stats_holder: BTreeMap::new(),
+            version_holder: env!("CARGO_PKG_VERSION").to_string(),
+            capabilities_holder: vec![
+                "stats".to_string(),
+                "stats_meta".to_string(),
+                "version".to_string(),
+                "monitor".to_string(),
+                "batch".to_string(),
+            ],
         }
     }
 
@@ -195,6 +649,18 @@ impl ScxStatsServer {
         self
     }
 
+    pub fn set_version(mut self, version: &str) -> Self {
+        self.version_holder = version.to_string();
+        self
+    }
+
+    pub fn add_capability(mut self, cap: &str) -> Self {
+        if !self.capabilities_holder.iter().any(|c| c == cap) {
+            self.capabilities_holder.push(cap.to_string());
+        }
+        self
+    }
+
     pub fn set_base_path<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.base_path = PathBuf::from(path.as_ref());
         self
@@ -215,7 +681,38 @@ impl ScxStatsServer {
         self
     }
 
-    pub fn launch(mut self) -> Result<Self> {
+    /// Binds a TCP socket instead of the default Unix domain socket, e.g.
+    /// for monitoring a scheduler running on another host or inside a VM.
+    /// Takes precedence over `set_path`/`set_base_path` if both are set.
+    ///
+    /// # Security
+    ///
+    /// The Unix socket transport is gated by filesystem permissions on the
+    /// socket path; this transport has **no authentication or ACL of any
+    /// kind** — anything that can reach `addr` can read scheduler stats.
+    /// Prefer a loopback or otherwise firewalled address, and put your own
+    /// auth (VPN, reverse proxy, etc.) in front of it before binding
+    /// anything reachable from an untrusted network.
+    pub fn set_tcp_addr(mut self, addr: SocketAddr) -> Self {
+        self.tcp_addr = Some(addr);
+        self
+    }
+
+    fn bind_listener(&mut self) -> Result<ScxStatsListener> {
+        if let Some(addr) = self.tcp_addr {
+            if !addr.ip().is_loopback() {
+                warn!(
+                    "binding scx_stats TCP listener on {addr}, which is not a loopback \
+                     address; this transport has no authentication, so anything able to \
+                     reach it can read scheduler stats"
+                );
+            }
+
+            let listener = TcpListener::bind(addr)
+                .with_context(|| format!("creating TCP socket {:?}", addr))?;
+            return Ok(ScxStatsListener::Tcp(listener));
+        }
+
         if self.path.is_none() {
             self.path = Some(self.base_path.join(&self.sched_path).join(&self.stats_path));
         }
@@ -234,13 +731,24 @@ impl ScxStatsServer {
 
         let listener =
             UnixListener::bind(path).with_context(|| format!("creating UNIX socket {:?}", path))?;
+        Ok(ScxStatsListener::Unix(listener))
+    }
+
+    pub fn launch(mut self) -> Result<Self> {
+        let listener = self.bind_listener()?;
 
         let mut stats_meta = BTreeMap::new();
         let mut stats = BTreeMap::new();
         std::mem::swap(&mut stats_meta, &mut self.stats_meta_holder);
         std::mem::swap(&mut stats, &mut self.stats_holder);
 
-        let inner = ScxStatsServerInner::new(listener, stats_meta, stats);
+        let inner = ScxStatsServerInner::new(
+            listener,
+            stats_meta,
+            stats,
+            self.version_holder.clone(),
+            self.capabilities_holder.clone(),
+        );
 
         spawn(move || inner.listen());
         Ok(self)
@@ -259,3 +767,221 @@ where
         Ok(serde_json::to_value(self)?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_with_stat(
+        name: &str,
+        fetch: impl FnMut(&BTreeMap<String, String>) -> Result<serde_json::Value> + Send + 'static,
+    ) -> Arc<Mutex<ScxStatsServerData>> {
+        let mut stats: StatMap = BTreeMap::new();
+        stats.insert(name.to_string(), Arc::new(Mutex::new(Box::new(fetch))));
+        Arc::new(Mutex::new(ScxStatsServerData {
+            stats_meta: BTreeMap::new(),
+            stats,
+            version: "test".to_string(),
+            capabilities: Vec::new(),
+        }))
+    }
+
+    #[test]
+    fn dispatch_batch_preserves_request_order_despite_uneven_latency() {
+        let mut stats: StatMap = BTreeMap::new();
+        stats.insert(
+            "slow".to_string(),
+            Arc::new(Mutex::new(Box::new(|_: &BTreeMap<String, String>| {
+                std::thread::sleep(Duration::from_millis(30));
+                Ok(serde_json::Value::String("slow".to_string()))
+            }) as Box<dyn FnMut(&BTreeMap<String, String>) -> Result<serde_json::Value> + Send>)),
+        );
+        stats.insert(
+            "fast".to_string(),
+            Arc::new(Mutex::new(Box::new(|_: &BTreeMap<String, String>| {
+                Ok(serde_json::Value::String("fast".to_string()))
+            }) as Box<dyn FnMut(&BTreeMap<String, String>) -> Result<serde_json::Value> + Send>)),
+        );
+        let data = Arc::new(Mutex::new(ScxStatsServerData {
+            stats_meta: BTreeMap::new(),
+            stats,
+            version: "test".to_string(),
+            capabilities: Vec::new(),
+        }));
+
+        // The first request is the slow one, so if ordering were simply
+        // "whichever thread finishes first", it would come back last.
+        let reqs = vec![
+            ScxStatsRequest::new("stats", vec![("target".to_string(), "slow".to_string())]),
+            ScxStatsRequest::new("stats", vec![("target".to_string(), "fast".to_string())]),
+        ];
+
+        let resps = ScxStatsServerInner::dispatch_batch(reqs, false, &data);
+
+        let targets: Vec<&str> = resps
+            .iter()
+            .map(|r| r.args["resp"].as_str().unwrap())
+            .collect();
+        assert_eq!(targets, vec!["slow", "fast"]);
+    }
+
+    #[test]
+    fn dispatch_batch_sequence_runs_in_order_too() {
+        let data = data_with_stat("echo", |args: &BTreeMap<String, String>| {
+            Ok(serde_json::Value::String(
+                args.get("tag").cloned().unwrap_or_default(),
+            ))
+        });
+
+        let reqs = vec![
+            ScxStatsRequest::new(
+                "stats",
+                vec![
+                    ("target".to_string(), "echo".to_string()),
+                    ("tag".to_string(), "first".to_string()),
+                ],
+            ),
+            ScxStatsRequest::new(
+                "stats",
+                vec![
+                    ("target".to_string(), "echo".to_string()),
+                    ("tag".to_string(), "second".to_string()),
+                ],
+            ),
+        ];
+
+        let resps = ScxStatsServerInner::dispatch_batch(reqs, true, &data);
+        assert_eq!(resps.len(), 2);
+        assert!(resps.iter().all(|r| r.errno == 0));
+        let tags: Vec<&str> = resps
+            .iter()
+            .map(|r| r.args["resp"].as_str().unwrap())
+            .collect();
+        assert_eq!(tags, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn monitor_stops_on_cancel_request() {
+        let data = data_with_stat("top", |_: &BTreeMap<String, String>| {
+            Ok(serde_json::Value::String("ok".to_string()))
+        });
+
+        let (server_stream, mut client_stream) = UnixStream::pair().unwrap();
+        let write_half: Box<dyn ScxStatsStream> = Box::new(server_stream.try_clone().unwrap());
+        let reader = BufReader::new(Box::new(server_stream) as Box<dyn ScxStatsStream>);
+
+        let req = ScxStatsRequest::new(
+            "monitor",
+            vec![
+                ("target".to_string(), "top".to_string()),
+                ("interval_ms".to_string(), "5".to_string()),
+            ],
+        );
+
+        let data_for_thread = data.clone();
+        let handle = spawn(move || {
+            ScxStatsServerInner::run_monitor(req, write_half, reader, &data_for_thread)
+        });
+
+        // Wait for at least one pushed response before cancelling.
+        let mut client_reader = BufReader::new(client_stream.try_clone().unwrap());
+        let mut line = String::new();
+        client_reader.read_line(&mut line).unwrap();
+        assert!(!line.is_empty());
+
+        let cancel = ScxStatsRequest::new("cancel", Vec::new());
+        client_stream
+            .write_all((serde_json::to_string(&cancel).unwrap() + "\n").as_bytes())
+            .unwrap();
+
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn monitor_stops_on_client_disconnect() {
+        let data = data_with_stat("top", |_: &BTreeMap<String, String>| {
+            Ok(serde_json::Value::String("ok".to_string()))
+        });
+
+        let (server_stream, client_stream) = UnixStream::pair().unwrap();
+        let write_half: Box<dyn ScxStatsStream> = Box::new(server_stream.try_clone().unwrap());
+        let reader = BufReader::new(Box::new(server_stream) as Box<dyn ScxStatsStream>);
+
+        let req = ScxStatsRequest::new(
+            "monitor",
+            vec![
+                ("target".to_string(), "top".to_string()),
+                ("interval_ms".to_string(), "5".to_string()),
+            ],
+        );
+
+        let data_for_thread = data.clone();
+        let handle = spawn(move || {
+            ScxStatsServerInner::run_monitor(req, write_half, reader, &data_for_thread)
+        });
+
+        // Dropping the client's end closes the connection, which should
+        // surface as EOF on the server's read half and end the loop.
+        drop(client_stream);
+
+        assert!(handle.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn classify_error_finds_json_errors_wrapped_with_extra_context() {
+        let json_err = serde_json::from_str::<ScxStatsRequest>("not json").unwrap_err();
+        let wrapped = anyhow::Error::new(json_err).context("while reading the next request");
+
+        let (errno, class) = classify_error(&wrapped);
+        assert_eq!(errno, libc::EINVAL);
+        assert_eq!(class.as_deref(), Some("InvalidData"));
+    }
+
+    fn version_resp(protocol_version: (u32, u32), capabilities: &[&str]) -> ScxStatsVersionResp {
+        ScxStatsVersionResp {
+            server_version: "test".to_string(),
+            protocol_version,
+            capabilities: capabilities.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn supports_matches_same_major_and_at_least_as_new_minor() {
+        let resp = version_resp((1, 2), &[]);
+        assert!(resp.supports((1, 0)));
+        assert!(resp.supports((1, 2)));
+    }
+
+    #[test]
+    fn supports_rejects_mismatched_major_or_newer_required_minor() {
+        let resp = version_resp((1, 2), &[]);
+        assert!(!resp.supports((2, 0)));
+        assert!(!resp.supports((1, 3)));
+    }
+
+    #[test]
+    fn has_capability_checks_the_advertised_list() {
+        let resp = version_resp((1, 0), &["stats", "batch"]);
+        assert!(resp.has_capability("batch"));
+        assert!(!resp.has_capability("monitor"));
+    }
+
+    #[test]
+    fn version_request_reports_server_version_and_capabilities() {
+        let data = data_with_stat("top", |_: &BTreeMap<String, String>| {
+            Ok(serde_json::Value::String("ok".to_string()))
+        });
+        data.lock().unwrap().version = "1.2.3".to_string();
+        data.lock().unwrap().capabilities = vec!["stats".to_string(), "batch".to_string()];
+
+        let req = ScxStatsRequest::new("version", Vec::new());
+        let resp = ScxStatsServerInner::eval_request(req, &data).unwrap();
+
+        let version: ScxStatsVersionResp =
+            serde_json::from_value(resp.args.get("resp").unwrap().clone()).unwrap();
+        assert_eq!(version.server_version, "1.2.3");
+        assert_eq!(version.protocol_version, SCX_STATS_PROTOCOL_VERSION);
+        assert!(version.has_capability("batch"));
+        assert!(!version.has_capability("monitor"));
+    }
+}